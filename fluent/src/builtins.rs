@@ -0,0 +1,132 @@
+//! Built-in functions available to every `MessageContext` without explicit
+//! registration: `NUMBER`.
+//!
+//! It takes a single positional argument and a bag of named arguments
+//! (e.g. `{ NUMBER($count, minimumFractionDigits: 2) }`) and returns a
+//! `FluentValue` carrying the requested format options, so `format()`
+//! renders it the same way a user-supplied function's result would be.
+//!
+//! There is no `DATETIME` yet: formatting a date needs a dedicated date
+//! type and real CLDR date-pattern data, neither of which this crate has.
+//! A built-in that only passed its argument through unchanged would look
+//! like it worked while silently dropping every named argument, so it's
+//! left unregistered until a `FluentType` date implementation exists to
+//! back it. Tracked as FOLLOWUP-1 in `TODO.md`.
+
+use std::collections::HashMap;
+
+use super::context::MessageContext;
+use super::types::{FluentNumber, FluentNumberStyle, FluentValue, PluralForm, MAX_DIGITS};
+
+/// Registers the `NUMBER` built-in on `ctx`.
+pub fn register_builtins(ctx: &mut MessageContext) {
+    ctx.add_function("NUMBER", number);
+}
+
+fn number(positional: &[FluentValue], named: &HashMap<String, FluentValue>) -> FluentValue {
+    let mut num = match positional.first() {
+        Some(FluentValue::Number(n)) => n.clone(),
+        Some(FluentValue::String(s)) => match s.parse::<FluentNumber>() {
+            Ok(n) => n,
+            Err(_) => return FluentValue::String(s.clone()),
+        },
+        _ => return FluentValue::String(String::new()),
+    };
+
+    if let Some(v) = named_usize(named, "minimumFractionDigits") {
+        num.options.minimum_fraction_digits = Some(v);
+    }
+    if let Some(v) = named_usize(named, "maximumFractionDigits") {
+        num.options.maximum_fraction_digits = Some(v);
+    }
+    if let Some(v) = named_usize(named, "minimumIntegerDigits") {
+        num.options.minimum_integer_digits = Some(v);
+    }
+    if let Some(FluentValue::String(style)) = named.get("style") {
+        num.options.style = match style.as_str() {
+            "currency" => FluentNumberStyle::Currency,
+            "percent" => FluentNumberStyle::Percent,
+            _ => FluentNumberStyle::Decimal,
+        };
+    }
+    if let Some(FluentValue::String(currency)) = named.get("currency") {
+        num.options.currency = Some(currency.clone());
+    }
+    if let Some(FluentValue::String(kind)) = named.get("type") {
+        num.options.plural_form = match kind.as_str() {
+            "ordinal" => PluralForm::Ordinal,
+            _ => PluralForm::Cardinal,
+        };
+    }
+    if let Some(FluentValue::String(use_grouping)) = named.get("useGrouping") {
+        num.options.use_grouping = use_grouping != "false";
+    }
+
+    FluentValue::Number(num)
+}
+
+fn named_usize(named: &HashMap<String, FluentValue>, key: &str) -> Option<usize> {
+    match named.get(key) {
+        Some(FluentValue::Number(n)) => Some((n.value.max(0.0) as usize).min(MAX_DIGITS)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_threads_named_args_into_options() {
+        let positional = [FluentValue::as_number("1234.5").unwrap()];
+        let mut named = HashMap::new();
+        named.insert("style".to_string(), FluentValue::from("currency"));
+        named.insert("currency".to_string(), FluentValue::from("USD"));
+        named.insert("minimumFractionDigits".to_string(), FluentValue::as_number("2").unwrap());
+        named.insert("type".to_string(), FluentValue::from("ordinal"));
+
+        let result = number(&positional, &named);
+        match result {
+            FluentValue::Number(n) => {
+                assert_eq!(n.options.style, FluentNumberStyle::Currency);
+                assert_eq!(n.options.currency.as_deref(), Some("USD"));
+                assert_eq!(n.options.minimum_fraction_digits, Some(2));
+                assert_eq!(n.options.plural_form, PluralForm::Ordinal);
+            }
+            other => panic!("expected a Number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn named_digit_counts_are_clamped() {
+        let positional = [FluentValue::as_number("1234.5").unwrap()];
+        let mut named = HashMap::new();
+        named.insert(
+            "maximumFractionDigits".to_string(),
+            FluentValue::as_number("309").unwrap(),
+        );
+
+        let result = number(&positional, &named);
+        match result {
+            FluentValue::Number(n) => {
+                assert_eq!(n.options.maximum_fraction_digits, Some(MAX_DIGITS));
+            }
+            other => panic!("expected a Number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn use_grouping_false_disables_grouping() {
+        let positional = [FluentValue::as_number("1234.5").unwrap()];
+        let mut named = HashMap::new();
+        named.insert("useGrouping".to_string(), FluentValue::from("false"));
+
+        let result = number(&positional, &named);
+        match result {
+            FluentValue::Number(n) => {
+                assert!(!n.options.use_grouping);
+            }
+            other => panic!("expected a Number, got {:?}", other),
+        }
+    }
+}
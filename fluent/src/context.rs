@@ -0,0 +1,150 @@
+//! `MessageContext` holds the state shared by every message formatted for a
+//! given set of locales: the locale negotiation list and the registry of
+//! functions (built-in and user-supplied) that `{ FOO($arg) }` call
+//! expressions are resolved against.
+//!
+//! The resolver (not part of this snapshot) looks up a call expression's
+//! name in this registry and invokes it with the call's positional and
+//! named arguments, both already evaluated to `FluentValue`s.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use super::builtins;
+use super::memoizer::IntlMemoizer;
+use super::types::FluentValue;
+
+/// The signature every registered function must implement: positional
+/// arguments, then named arguments, producing a `FluentValue`.
+pub type FluentFunction = Box<dyn Fn(&[FluentValue], &HashMap<String, FluentValue>) -> FluentValue>;
+
+/// The signature of a pseudo-localization transform installed via
+/// [`MessageContext::set_transform`][].
+///
+/// [`MessageContext::set_transform`]: struct.MessageContext.html#method.set_transform
+pub type TransformFn = Box<dyn Fn(&str) -> Cow<'static, str>>;
+
+pub struct MessageContext {
+    /// The locales requested for this context, most-preferred first.
+    pub locales: Vec<String>,
+    functions: HashMap<String, FluentFunction>,
+    /// Cache of per-locale `intl` instances (plural rules, and number
+    /// formatters once those exist) used by `FluentValue::matches`.
+    pub(crate) intls: IntlMemoizer,
+    transform: Option<TransformFn>,
+}
+
+impl MessageContext {
+    /// Creates a new context for `locales`, pre-populated with the
+    /// built-in `NUMBER` function.
+    pub fn new<S: ToString>(locales: &[S]) -> Self {
+        let mut ctx = MessageContext {
+            locales: locales.iter().map(|s| s.to_string()).collect(),
+            functions: HashMap::new(),
+            intls: IntlMemoizer::new(),
+            transform: None,
+        };
+        builtins::register_builtins(&mut ctx);
+        ctx
+    }
+
+    /// Borrows `locales` as `&str`s, the form `fluent_locale::negotiate_languages`
+    /// requires; `self.locales` itself stays `Vec<String>` since it's the
+    /// form callers construct a context with.
+    pub fn locale_refs(&self) -> Vec<&str> {
+        self.locales.iter().map(String::as_str).collect()
+    }
+
+    /// Installs a pseudo-localization transform (see the [`transform`][]
+    /// module for the built-in `accented` and `bidi` ones) to be applied
+    /// to a message's own `TextElement` content before it's returned from
+    /// formatting. Does not affect interpolated argument values: a
+    /// `NUMBER`-formatted or `String` argument passed through
+    /// [`FluentValue::format`][] is never run through this transform,
+    /// since [`FluentValue::format`][] renders an argument's own content,
+    /// not message text.
+    ///
+    /// This means the transform is currently inert for any caller that
+    /// only drives `FluentValue::format` directly — it only takes effect
+    /// once a resolver walks a message's AST and calls
+    /// [`transform_text`][] on each `TextElement`, which this crate
+    /// doesn't ship yet. Tracked as FOLLOWUP-2 in `TODO.md`.
+    ///
+    /// [`transform`]: ../transform/index.html
+    /// [`FluentValue::format`]: ../types/enum.FluentValue.html#method.format
+    /// [`transform_text`]: struct.MessageContext.html#method.transform_text
+    pub fn set_transform<F>(&mut self, transform: F)
+    where
+        F: Fn(&str) -> Cow<'static, str> + 'static,
+    {
+        self.transform = Some(Box::new(transform));
+    }
+
+    /// Removes any previously installed transform.
+    pub fn clear_transform(&mut self) {
+        self.transform = None;
+    }
+
+    /// Applies the installed transform (if any) to `text`. Intended to be
+    /// called by the resolver on a message's own `TextElement` content,
+    /// not on substituted argument values.
+    pub fn transform_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.transform {
+            Some(transform) => match transform(text) {
+                Cow::Borrowed(s) => Cow::Borrowed(s),
+                Cow::Owned(s) => Cow::Owned(s),
+            },
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    /// Registers `func` as the implementation of `{ NAME(...) }` call
+    /// expressions. Overwrites any existing function (built-in or
+    /// user-defined) of the same name.
+    pub fn add_function<F>(&mut self, name: &str, func: F)
+    where
+        F: Fn(&[FluentValue], &HashMap<String, FluentValue>) -> FluentValue + 'static,
+    {
+        self.functions.insert(name.to_string(), Box::new(func));
+    }
+
+    /// Looks up the function registered under `name`, if any.
+    pub fn get_function(&self, name: &str) -> Option<&FluentFunction> {
+        self.functions.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_function_is_retrievable_and_callable_via_get_function() {
+        let mut ctx = MessageContext::new(&["en"]);
+        ctx.add_function("GREET", |_positional, _named| {
+            FluentValue::String("hello".to_string())
+        });
+
+        let func = ctx.get_function("GREET").expect("function should be registered");
+        let result = func(&[], &HashMap::new());
+        assert_eq!(result, FluentValue::String("hello".to_string()));
+    }
+
+    #[test]
+    fn get_function_is_none_for_unregistered_name() {
+        let ctx = MessageContext::new(&["en"]);
+        assert!(ctx.get_function("NOPE").is_none());
+    }
+
+    #[test]
+    fn set_transform_transform_text_clear_transform_round_trip() {
+        let mut ctx = MessageContext::new(&["en"]);
+        assert_eq!(ctx.transform_text("hello"), Cow::Borrowed("hello"));
+
+        ctx.set_transform(|text| Cow::Owned(text.to_uppercase()));
+        assert_eq!(ctx.transform_text("hello"), Cow::Owned::<str>("HELLO".to_string()));
+
+        ctx.clear_transform();
+        assert_eq!(ctx.transform_text("hello"), Cow::Borrowed("hello"));
+    }
+}
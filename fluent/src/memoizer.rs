@@ -0,0 +1,82 @@
+//! Lazily constructs and caches per-locale `intl` instances so hot paths
+//! like `FluentValue::matches` don't pay for a fresh `IntlPluralRules` on
+//! every single comparison.
+//!
+//! Owned by [`MessageContext`][], keyed by locale and split per
+//! `PluralRuleType` (the upstream enum isn't `Hash`/`Eq`, only `Copy`, so
+//! it can't be folded into a single map's key).
+//!
+//! [`MessageContext`]: ../context/struct.MessageContext.html
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use intl_pluralrules::{IntlPluralRules, PluralRuleType};
+
+#[cfg(test)]
+use intl_pluralrules::PluralCategory;
+
+#[derive(Default)]
+pub struct IntlMemoizer {
+    cardinal_rules: RefCell<HashMap<String, IntlPluralRules>>,
+    ordinal_rules: RefCell<HashMap<String, IntlPluralRules>>,
+}
+
+impl IntlMemoizer {
+    pub fn new() -> Self {
+        IntlMemoizer::default()
+    }
+
+    /// Runs `cb` against the `IntlPluralRules` cached for `(locale,
+    /// rule_type)`, constructing and caching one first if this is the
+    /// first time it's been requested. Returns `Err` instead of panicking
+    /// if construction fails.
+    pub fn with_try_get_plural_rules<F, R>(
+        &self,
+        locale: &str,
+        rule_type: PluralRuleType,
+        cb: F,
+    ) -> Result<R, String>
+    where
+        F: FnOnce(&IntlPluralRules) -> R,
+    {
+        let cache = match rule_type {
+            PluralRuleType::CARDINAL => &self.cardinal_rules,
+            PluralRuleType::ORDINAL => &self.ordinal_rules,
+        };
+
+        if !cache.borrow().contains_key(locale) {
+            let pr = IntlPluralRules::create(locale, rule_type)
+                .map_err(|e| format!("failed to create IntlPluralRules: {:?}", e))?;
+            cache.borrow_mut().insert(locale.to_string(), pr);
+        }
+
+        let borrowed = cache.borrow();
+        Ok(cb(borrowed.get(locale).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_distinct_rules_per_rule_type() {
+        let memoizer = IntlMemoizer::new();
+
+        // en CARDINAL(21) = "other" but ORDINAL(21) = "one" ("21st"); if the
+        // two rule types shared a cache slot one of these would come back
+        // wrong.
+        let cardinal = memoizer
+            .with_try_get_plural_rules("en", PluralRuleType::CARDINAL, |pr| {
+                pr.select(21.0).unwrap()
+            })
+            .unwrap();
+        let ordinal = memoizer
+            .with_try_get_plural_rules("en", PluralRuleType::ORDINAL, |pr| pr.select(21.0).unwrap())
+            .unwrap();
+
+        assert_eq!(cardinal, PluralCategory::OTHER);
+        assert_eq!(ordinal, PluralCategory::ONE);
+    }
+}
@@ -0,0 +1,123 @@
+//! Pseudo-localization transforms for exercising layout and bidi handling
+//! in untranslated UI.
+//!
+//! A transform is installed on [`MessageContext`][] via
+//! [`MessageContext::set_transform`][] and applied by
+//! `MessageContext::transform_text`, which is for the text produced by a
+//! translation (e.g. a message's `TextElement`s), not for interpolated
+//! argument values, matching how other pseudo-loc tools distinguish source
+//! text from data. Nothing in this crate calls `transform_text` on
+//! formatted output, so installing a transform does not change what
+//! `FluentValue::format` returns.
+//!
+//! [`MessageContext`]: ../context/struct.MessageContext.html
+//! [`MessageContext::set_transform`]: ../context/struct.MessageContext.html#method.set_transform
+
+use std::borrow::Cow;
+
+/// Maps ASCII letters to accented look-alikes (`a` -> `à`, `e` -> `è`, ...)
+/// and pads the result to roughly `1.3x` its original length with filler
+/// characters, to surface both missing-accent and text-truncation bugs.
+pub fn accented(input: &str) -> Cow<'static, str> {
+    let mut out = String::with_capacity(input.len() + input.len() / 3);
+    for c in input.chars() {
+        out.push(accented_char(c));
+    }
+
+    let target_len = (input.chars().count() as f32 * 1.3).ceil() as usize;
+    let padding_needed = target_len.saturating_sub(out.chars().count());
+    if padding_needed > 0 {
+        out.push(' ');
+        out.extend(std::iter::repeat('~').take(padding_needed.saturating_sub(1)));
+    }
+
+    Cow::Owned(out)
+}
+
+fn accented_char(c: char) -> char {
+    if c.is_ascii_uppercase() {
+        let lower = accented_lower(c.to_ascii_lowercase());
+        return lower.to_uppercase().next().unwrap_or(lower);
+    }
+    accented_lower(c)
+}
+
+fn accented_lower(c: char) -> char {
+    match c {
+        'a' => 'à',
+        'b' => 'ƀ',
+        'c' => 'ç',
+        'd' => 'ð',
+        'e' => 'è',
+        'f' => 'ƒ',
+        'g' => 'ĝ',
+        'h' => 'ĥ',
+        'i' => 'ì',
+        'j' => 'ĵ',
+        'k' => 'ķ',
+        'l' => 'ļ',
+        'm' => 'ɱ',
+        'n' => 'ñ',
+        'o' => 'ò',
+        'p' => 'ƥ',
+        'q' => 'ɋ',
+        'r' => 'ŕ',
+        's' => 'ś',
+        't' => 'ţ',
+        'u' => 'ù',
+        'v' => 'ṽ',
+        'w' => 'ŵ',
+        'x' => 'ẋ',
+        'y' => 'ý',
+        'z' => 'ź',
+        other => other,
+    }
+}
+
+/// Wraps `input` in Unicode directional isolate marks (FSI/PDI) to expose
+/// bugs where surrounding UI doesn't correctly isolate bidirectional text.
+pub fn bidi(input: &str) -> Cow<'static, str> {
+    const FSI: char = '\u{2068}';
+    const PDI: char = '\u{2069}';
+
+    let mut out = String::with_capacity(input.len() + 6);
+    out.push(FSI);
+    out.push_str(input);
+    out.push(PDI);
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accented_maps_ascii_letters() {
+        assert_eq!(accented("ab"), "àƀ ");
+    }
+
+    #[test]
+    fn accented_maps_uppercase_consonants() {
+        assert_eq!(accented_char('B'), 'Ƀ');
+        assert_eq!(accented_char('N'), 'Ñ');
+        assert_eq!(accented_char('Z'), 'Ź');
+    }
+
+    #[test]
+    fn accented_leaves_non_letters_untouched() {
+        assert!(accented("1 2!").starts_with("1 2!"));
+    }
+
+    #[test]
+    fn accented_pads_to_roughly_1_3x_length() {
+        let input = "hello world";
+        let out = accented(input);
+        let target = (input.chars().count() as f32 * 1.3).ceil() as usize;
+        assert_eq!(out.chars().count(), target);
+    }
+
+    #[test]
+    fn bidi_wraps_in_isolate_marks() {
+        assert_eq!(bidi("hello"), "\u{2068}hello\u{2069}");
+    }
+}
@@ -18,40 +18,349 @@ extern crate intl_pluralrules;
 use self::intl_pluralrules::{IntlPluralRules, PluralCategory, PluralRuleType};
 
 use std::f32;
+use std::fmt;
 use std::num::ParseFloatError;
 use std::str::FromStr;
 
 use super::context::MessageContext;
 
 /// Value types which can be formatted to a String.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug)]
 pub enum FluentValue {
     /// Fluent String type.
     String(String),
-    /// Fluent Number type.
-    Number(String),
+    /// Fluent Number type, carrying both the parsed value and the options
+    /// that control how it is rendered.
+    Number(FluentNumber),
+    /// An application-defined value (dates, durations, money, ...) that
+    /// knows how to format and compare itself through the [`FluentType`][]
+    /// trait rather than being baked into this enum.
+    ///
+    /// [`FluentType`]: trait.FluentType.html
+    Custom(Box<dyn FluentType>),
+}
+
+impl Clone for FluentValue {
+    fn clone(&self) -> Self {
+        match self {
+            FluentValue::String(s) => FluentValue::String(s.clone()),
+            FluentValue::Number(n) => FluentValue::Number(n.clone()),
+            FluentValue::Custom(c) => FluentValue::Custom(c.duplicate()),
+        }
+    }
+}
+
+impl PartialEq for FluentValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (FluentValue::String(a), FluentValue::String(b)) => a == b,
+            (FluentValue::Number(a), FluentValue::Number(b)) => a == b,
+            (FluentValue::Custom(a), FluentValue::Custom(b)) => a.as_string() == b.as_string(),
+            _ => false,
+        }
+    }
+}
+
+/// A trait for application-defined value types that can be carried by
+/// [`FluentValue::Custom`][] and formatted/matched through the context's
+/// locale without the type being known to this crate.
+///
+/// [`FluentValue::Custom`]: enum.FluentValue.html#variant.Custom
+pub trait FluentType: fmt::Debug {
+    /// Renders this value for `ctx`'s negotiated locale.
+    fn format(&self, ctx: &MessageContext) -> String;
+    /// Clones this value into a new boxed trait object, since
+    /// `FluentValue` itself needs to be `Clone`.
+    fn duplicate(&self) -> Box<dyn FluentType>;
+    /// A locale-independent string representation used for selector
+    /// matching.
+    fn as_string(&self) -> String;
+}
+
+impl From<Box<dyn FluentType>> for FluentValue {
+    fn from(v: Box<dyn FluentType>) -> Self {
+        FluentValue::Custom(v)
+    }
+}
+
+/// A parsed numeric value plus the options that govern its formatting.
+///
+/// Constructing one from a written FTL number literal (via [`FromStr`][])
+/// infers `minimum_fraction_digits`/`minimum_integer_digits` from the
+/// literal's own precision, e.g. `1.00` implies `minimum_fraction_digits: 2`
+/// and `01` implies `minimum_integer_digits: 2`.
+///
+/// [`FromStr`]: https://doc.rust-lang.org/std/str/trait.FromStr.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct FluentNumber {
+    /// The parsed numeric value.
+    pub value: f64,
+    /// Formatting options for this value.
+    pub options: FluentNumberOptions,
+}
+
+/// Options controlling how a [`FluentNumber`][] is rendered by
+/// [`FluentValue::format`][].
+///
+/// [`FluentNumber`]: struct.FluentNumber.html
+/// [`FluentValue::format`]: enum.FluentValue.html#method.format
+#[derive(Clone, Debug, PartialEq)]
+pub struct FluentNumberOptions {
+    /// The smallest number of fraction digits to render, padding with
+    /// trailing zeros if necessary.
+    pub minimum_fraction_digits: Option<usize>,
+    /// The largest number of fraction digits to render; additional digits
+    /// are rounded away.
+    pub maximum_fraction_digits: Option<usize>,
+    /// The smallest number of integer digits to render, padding with
+    /// leading zeros if necessary.
+    pub minimum_integer_digits: Option<usize>,
+    /// Whether to render the value as a plain decimal, a currency amount,
+    /// or a percentage.
+    pub style: FluentNumberStyle,
+    /// Whether to insert grouping separators (a `,` every three digits,
+    /// for every locale) into the integer part.
+    pub use_grouping: bool,
+    /// The ISO 4217 currency code to render alongside a `Currency`-style
+    /// value, e.g. `"USD"`. Locale-correct symbol choice and placement
+    /// need real CLDR currency data we don't have here, so a `Currency`
+    /// style with no code set renders as a plain decimal rather than
+    /// guessing at a symbol.
+    pub currency: Option<String>,
+    /// Whether this number selects CLDR plural categories as a cardinal
+    /// (`1 apple`) or an ordinal (`1st place`). Selector matching uses
+    /// this to decide which plural rule set applies — a bare keyword like
+    /// `one` doesn't say which one the message means.
+    pub plural_form: PluralForm,
+}
+
+impl Default for FluentNumberOptions {
+    fn default() -> Self {
+        FluentNumberOptions {
+            minimum_fraction_digits: None,
+            maximum_fraction_digits: None,
+            minimum_integer_digits: None,
+            style: FluentNumberStyle::Decimal,
+            use_grouping: true,
+            currency: None,
+            plural_form: PluralForm::Cardinal,
+        }
+    }
+}
+
+/// Which CLDR plural rule set a number's plural category should be read
+/// against. Set via `NUMBER($n, type: "ordinal")`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PluralForm {
+    /// `1 apple`, `2 apples`.
+    Cardinal,
+    /// `1st place`, `2nd place`.
+    Ordinal,
+}
+
+/// The kind of number a [`FluentNumber`][] represents.
+///
+/// [`FluentNumber`]: struct.FluentNumber.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FluentNumberStyle {
+    /// A plain decimal number, e.g. `1234.5`.
+    Decimal,
+    /// A currency amount, e.g. `USD 1,234.50`.
+    Currency,
+    /// A percentage, e.g. `50%`.
+    Percent,
+}
+
+/// The largest digit count `format()` honors for any of
+/// `minimum_fraction_digits`/`maximum_fraction_digits`/
+/// `minimum_integer_digits`, matching ECMA-402's own limit. At 309 digits
+/// and above, `10f64.powi(digits)` in `round_to` overflows to infinity and
+/// produces `NaN` output, so values above this limit are clamped rather
+/// than trusted as-is.
+///
+/// `pub(crate)` so `builtins.rs` can apply the same clamp to the named
+/// digit-count arguments it parses, rather than the two limits risking
+/// drifting apart if this value ever changes.
+pub(crate) const MAX_DIGITS: usize = 20;
+
+impl FluentNumber {
+    /// Creates a `FluentNumber` from an already-parsed value and explicit
+    /// options, bypassing literal-precision inference.
+    pub fn new(value: f64, options: FluentNumberOptions) -> Self {
+        FluentNumber { value, options }
+    }
+
+    /// Renders this number for `ctx`'s negotiated locale, honoring
+    /// `self.options`.
+    pub fn format(&self, ctx: &MessageContext) -> String {
+        let min_frac = self
+            .options
+            .minimum_fraction_digits
+            .unwrap_or(0)
+            .min(MAX_DIGITS);
+        let max_frac = self
+            .options
+            .maximum_fraction_digits
+            .map(|d| d.min(MAX_DIGITS))
+            .unwrap_or_else(|| min_frac.max(3));
+        let max_frac = max_frac.max(min_frac);
+
+        let value = if self.options.style == FluentNumberStyle::Percent {
+            self.value * 100.0
+        } else {
+            self.value
+        };
+
+        let rounded = round_to(value, max_frac);
+        let is_negative = rounded.is_sign_negative() && rounded != 0.0;
+        let magnitude = rounded.abs();
+
+        let formatted = format!("{:.*}", max_frac, magnitude);
+        let (mut int_part, frac_part) = match formatted.split_once('.') {
+            Some((i, f)) => (i.to_string(), f.to_string()),
+            None => (formatted, String::new()),
+        };
+
+        let frac_part = trim_trailing_zeros(&frac_part, min_frac);
+
+        if let Some(min_int) = self.options.minimum_integer_digits {
+            let min_int = min_int.min(MAX_DIGITS);
+            while int_part.len() < min_int {
+                int_part.insert(0, '0');
+            }
+        }
+
+        if self.options.use_grouping {
+            int_part = group_digits(&int_part, ctx);
+        }
+
+        let mut out = String::new();
+        if is_negative {
+            out.push('-');
+        }
+        if self.options.style == FluentNumberStyle::Currency {
+            if let Some(code) = &self.options.currency {
+                out.push_str(code);
+                out.push(' ');
+            }
+        }
+        out.push_str(&int_part);
+        if !frac_part.is_empty() {
+            out.push('.');
+            out.push_str(&frac_part);
+        }
+        if self.options.style == FluentNumberStyle::Percent {
+            out.push('%');
+        }
+        out
+    }
+}
+
+/// Rounds `value` to `digits` fractional digits (half-up).
+fn round_to(value: f64, digits: usize) -> f64 {
+    let factor = 10f64.powi(digits as i32);
+    (value * factor).round() / factor
+}
+
+/// Drops trailing zeros from a rendered fraction, but never below `min`
+/// digits.
+fn trim_trailing_zeros(frac: &str, min: usize) -> String {
+    let mut trimmed = frac.trim_end_matches('0').to_string();
+    while trimmed.len() < min {
+        trimmed.push('0');
+    }
+    trimmed
+}
+
+/// Inserts a `,` grouping separator every three digits into an integer
+/// digit string. The separator and group size are the same for every
+/// locale: `_ctx` is not consulted and no CLDR grouping data is used.
+fn group_digits(int_part: &str, _ctx: &MessageContext) -> String {
+    let bytes = int_part.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len() + bytes.len() / 3);
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 && (bytes.len() - i) % 3 == 0 {
+            out.push(b',');
+        }
+        out.push(*b);
+    }
+    String::from_utf8(out).unwrap()
+}
+
+impl FromStr for FluentNumber {
+    type Err = ParseFloatError;
+
+    /// Parses a written FTL number literal, inferring `options` from its
+    /// precision: `1.00` yields `minimum_fraction_digits: 2`, `01` yields
+    /// `minimum_integer_digits: 2`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let value = f64::from_str(input)?;
+
+        let mut options = FluentNumberOptions::default();
+
+        let unsigned = input.trim_start_matches('-');
+        match unsigned.split_once('.') {
+            Some((int_part, frac_part)) => {
+                options.minimum_fraction_digits = Some(frac_part.len());
+                if int_part.len() > 1 && int_part.starts_with('0') {
+                    options.minimum_integer_digits = Some(int_part.len());
+                }
+            }
+            None => {
+                if unsigned.len() > 1 && unsigned.starts_with('0') {
+                    options.minimum_integer_digits = Some(unsigned.len());
+                }
+            }
+        }
+
+        Ok(FluentNumber::new(value, options))
+    }
+}
+
+/// Negotiates a locale for `rule_type` and checks whether `value` selects
+/// `cat` under that rule.
+fn matches_plural_category(
+    ctx: &MessageContext,
+    rule_type: PluralRuleType,
+    value: f64,
+    cat: &PluralCategory,
+) -> bool {
+    let locales = negotiate_languages(
+        &ctx.locale_refs(),
+        IntlPluralRules::get_locales(rule_type),
+        Some("en"),
+        &NegotiationStrategy::Lookup,
+    );
+
+    // `negotiate_languages` always returns at least the `Some("en")`
+    // fallback above, so `locales[0]` can't panic today; that's an
+    // invariant of the call above, not of this function, so assert it
+    // rather than relying on it silently.
+    debug_assert!(!locales.is_empty(), "negotiate_languages should always return a fallback");
+
+    ctx.intls
+        .with_try_get_plural_rules(locales[0], rule_type, |pr| pr.select(value).as_ref() == Ok(cat))
+        .unwrap_or(false)
 }
 
 impl FluentValue {
     pub fn as_number<S: ToString>(v: S) -> Result<Self, ParseFloatError> {
-        f64::from_str(&v.to_string()).map(|_| FluentValue::Number(v.to_string()))
+        FluentNumber::from_str(&v.to_string()).map(FluentValue::Number)
     }
 
-    pub fn format(&self, _ctx: &MessageContext) -> String {
+    pub fn format(&self, ctx: &MessageContext) -> String {
         match self {
             FluentValue::String(s) => s.clone(),
-            FluentValue::Number(n) => n.clone(),
+            FluentValue::Number(n) => n.format(ctx),
+            FluentValue::Custom(c) => c.format(ctx),
         }
     }
 
     pub fn matches(&self, ctx: &MessageContext, other: &FluentValue) -> bool {
         match (self, other) {
             (&FluentValue::String(ref a), &FluentValue::String(ref b)) => a == b,
-            (&FluentValue::Number(ref a), &FluentValue::Number(ref b)) => a == b,
+            (&FluentValue::Number(ref a), &FluentValue::Number(ref b)) => a.value == b.value,
             (&FluentValue::String(ref a), &FluentValue::Number(ref b)) => {
-                println!("Number: {:#?}", b);
-                //XXX: This is a dirty hack and should be replaced with a
-                //lazy resolved cache on the context.
                 let cat = match a.as_str() {
                     "zero" => PluralCategory::ZERO,
                     "one" => PluralCategory::ONE,
@@ -62,17 +371,21 @@ impl FluentValue {
                     _ => return false,
                 };
 
-                let locales = negotiate_languages(
-                    ctx.locales,
-                    IntlPluralRules::get_locales(PluralRuleType::CARDINAL),
-                    Some("en"),
-                    &NegotiationStrategy::Lookup,
-                );
+                let rule_type = match b.options.plural_form {
+                    PluralForm::Cardinal => PluralRuleType::CARDINAL,
+                    PluralForm::Ordinal => PluralRuleType::ORDINAL,
+                };
 
-                let pr = IntlPluralRules::create(locales[0], PluralRuleType::CARDINAL).unwrap();
-                pr.select(&b) == Ok(cat)
+                matches_plural_category(ctx, rule_type, b.value, &cat)
             }
             (&FluentValue::Number(..), &FluentValue::String(..)) => false,
+            (&FluentValue::Custom(ref a), &FluentValue::String(ref b)) => &a.as_string() == b,
+            (&FluentValue::String(ref a), &FluentValue::Custom(ref b)) => a == &b.as_string(),
+            (&FluentValue::Custom(ref a), &FluentValue::Custom(ref b)) => {
+                a.as_string() == b.as_string()
+            }
+            (&FluentValue::Custom(..), &FluentValue::Number(..))
+            | (&FluentValue::Number(..), &FluentValue::Custom(..)) => false,
         }
     }
 }
@@ -91,12 +404,160 @@ impl<'a> From<&'a str> for FluentValue {
 
 impl From<f32> for FluentValue {
     fn from(n: f32) -> Self {
-        FluentValue::Number(n.to_string())
+        FluentValue::Number(FluentNumber::new(n as f64, FluentNumberOptions::default()))
     }
 }
 
 impl From<i8> for FluentValue {
     fn from(n: i8) -> Self {
-        FluentValue::Number(n.to_string())
+        FluentValue::Number(FluentNumber::new(n as f64, FluentNumberOptions::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    struct TestDate(i64);
+
+    impl FluentType for TestDate {
+        fn format(&self, _ctx: &MessageContext) -> String {
+            format!("day {}", self.0)
+        }
+
+        fn duplicate(&self) -> Box<dyn FluentType> {
+            Box::new(self.clone())
+        }
+
+        fn as_string(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    #[test]
+    fn custom_format_dispatches_through_the_trait() {
+        let ctx = MessageContext::new(&["en"]);
+        let value = FluentValue::Custom(Box::new(TestDate(3)));
+        assert_eq!(value.format(&ctx), "day 3");
+    }
+
+    #[test]
+    fn custom_matches_string_both_directions() {
+        let ctx = MessageContext::new(&["en"]);
+        let custom = FluentValue::Custom(Box::new(TestDate(3)));
+        let string = FluentValue::String("3".to_string());
+
+        assert!(custom.matches(&ctx, &string));
+        assert!(string.matches(&ctx, &custom));
+
+        let other = FluentValue::String("4".to_string());
+        assert!(!custom.matches(&ctx, &other));
+    }
+
+    #[test]
+    fn custom_clone_round_trips() {
+        let original = FluentValue::Custom(Box::new(TestDate(3)));
+        let cloned = original.clone();
+
+        match (&original, &cloned) {
+            (FluentValue::Custom(a), FluentValue::Custom(b)) => {
+                assert_eq!(a.as_string(), b.as_string())
+            }
+            _ => panic!("expected both values to stay Custom"),
+        }
+    }
+
+    #[test]
+    fn literal_precision_infers_fraction_digits() {
+        let n: FluentNumber = "1.00".parse().unwrap();
+        assert_eq!(n.value, 1.0);
+        assert_eq!(n.options.minimum_fraction_digits, Some(2));
+    }
+
+    #[test]
+    fn literal_precision_infers_integer_digits() {
+        let n: FluentNumber = "01".parse().unwrap();
+        assert_eq!(n.options.minimum_integer_digits, Some(2));
+    }
+
+    #[test]
+    fn currency_style_without_code_renders_plain_decimal() {
+        let ctx = MessageContext::new(&["en"]);
+        let mut options = FluentNumberOptions::default();
+        options.style = FluentNumberStyle::Currency;
+        let n = FluentNumber::new(12.5, options);
+        assert_eq!(n.format(&ctx), "12.5");
+    }
+
+    #[test]
+    fn currency_style_with_code_prefixes_it() {
+        let ctx = MessageContext::new(&["en"]);
+        let mut options = FluentNumberOptions::default();
+        options.style = FluentNumberStyle::Currency;
+        options.currency = Some("USD".to_string());
+        let n = FluentNumber::new(12.5, options);
+        assert_eq!(n.format(&ctx), "USD 12.5");
+    }
+
+    #[test]
+    fn percent_style_scales_by_100() {
+        let ctx = MessageContext::new(&["en"]);
+        let mut options = FluentNumberOptions::default();
+        options.style = FluentNumberStyle::Percent;
+        let n = FluentNumber::new(0.5, options);
+        assert_eq!(n.format(&ctx), "50%");
+    }
+
+    #[test]
+    fn absurd_fraction_digits_are_clamped_instead_of_overflowing() {
+        let ctx = MessageContext::new(&["en"]);
+        let mut options = FluentNumberOptions::default();
+        options.maximum_fraction_digits = Some(309);
+        let n = FluentNumber::new(1234.5, options);
+        assert!(!n.format(&ctx).contains("NaN"));
+    }
+
+    #[test]
+    fn format_groups_and_pads() {
+        let ctx = MessageContext::new(&["en"]);
+        let n: FluentNumber = "1234.5".parse().unwrap();
+        assert_eq!(n.format(&ctx), "1,234.5");
+    }
+
+    #[test]
+    fn numeric_equality_ignores_written_precision() {
+        let ctx = MessageContext::new(&["en"]);
+        let a = FluentValue::as_number("1.0").unwrap();
+        let b = FluentValue::as_number("1").unwrap();
+        assert!(a.matches(&ctx, &b));
+    }
+
+    #[test]
+    fn cardinal_keyword_matches_cardinal_rule() {
+        let ctx = MessageContext::new(&["en"]);
+        let selector = FluentValue::String("one".to_string());
+        let arg = FluentValue::as_number("1").unwrap();
+        assert!(selector.matches(&ctx, &arg));
+
+        // en CARDINAL(21) = "other", unlike ORDINAL(21) = "one".
+        let arg21 = FluentValue::as_number("21").unwrap();
+        assert!(!selector.matches(&ctx, &arg21));
+    }
+
+    #[test]
+    fn ordinal_keyword_matches_ordinal_rule_only_when_requested() {
+        let ctx = MessageContext::new(&["en"]);
+        let selector = FluentValue::String("one".to_string());
+
+        let mut ordinal_options = FluentNumberOptions::default();
+        ordinal_options.plural_form = PluralForm::Ordinal;
+        let arg21 = FluentValue::Number(FluentNumber::new(21.0, ordinal_options));
+
+        assert!(selector.matches(&ctx, &arg21));
+
+        // Without the ordinal form, 21 is read as a cardinal ("other").
+        let cardinal21 = FluentValue::as_number("21").unwrap();
+        assert!(!selector.matches(&ctx, &cardinal21));
     }
 }